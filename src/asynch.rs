@@ -0,0 +1,185 @@
+//! Async counterpart of [`crate::Eeprom`] built on `embedded-hal-async`.
+//!
+//! The blocking driver busy-delays through the EEPROM's internal write cycle, which stalls an
+//! executor for the duration. `AsyncEeprom` instead `.await`s each page transfer and the
+//! write-cycle wait, so executors like Embassy can run other tasks in the meantime.
+
+use core::marker::PhantomData;
+use embedded_hal::digital::v2::OutputPin;
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::i2c::I2c;
+
+use crate::device::{DeviceKind, Mcp24Lc512};
+use crate::{block_chunk_len, page_chunk_len, DEFAULT_ADDRESS, FIXED_DELAY_MS, MAX_ADDRESS_BYTES, MAX_PAGE_SIZE};
+
+/// Errors, mirroring [`crate::Error`] but for the single `I2c::Error` type used by
+/// `embedded-hal-async`.
+pub enum AsyncError<E> {
+    OutOfRange,
+    TooMuchData,
+    I2c(E),
+}
+
+pub struct AsyncEeprom<I2C: I2c, WP: OutputPin, DELAY: DelayNs, D: DeviceKind = Mcp24Lc512> {
+    address: u8,
+    i2c: I2C,
+    wp: WP,
+    delay: DELAY,
+    _device: PhantomData<D>,
+}
+
+impl<I2C: I2c, WP: OutputPin, DELAY: DelayNs, D: DeviceKind> AsyncEeprom<I2C, WP, DELAY, D> {
+    pub fn new(i2c: I2C, wp: WP, delay: DELAY) -> Self {
+        AsyncEeprom {
+            i2c,
+            address: DEFAULT_ADDRESS,
+            wp,
+            delay,
+            _device: PhantomData,
+        }
+    }
+
+    fn device_address(&self, addr: u32) -> u8 {
+        if D::CAPACITY > 0x1_0000 {
+            self.address | ((addr >> 16) as u8)
+        } else {
+            self.address
+        }
+    }
+
+    fn word_address(&self, addr: u32, buf: &mut [u8; MAX_ADDRESS_BYTES]) {
+        if D::ADDRESS_BYTES == 1 {
+            buf[0] = addr as u8;
+        } else {
+            buf[0..2].copy_from_slice(&(addr as u16).to_be_bytes());
+        }
+    }
+
+    /// Await the device's internal write cycle instead of busy-delaying.
+    async fn ack_delay(&mut self) {
+        self.delay.delay_ms(FIXED_DELAY_MS).await;
+    }
+
+    pub async fn write_byte(&mut self, addr: u32, byte: u8) -> Result<(), AsyncError<I2C::Error>> {
+        if addr as usize > D::CAPACITY {
+            return Err(AsyncError::OutOfRange);
+        }
+        if addr as usize + 1 > D::CAPACITY {
+            return Err(AsyncError::TooMuchData);
+        }
+
+        let device_address = self.device_address(addr);
+        let mut word_addr = [0u8; MAX_ADDRESS_BYTES];
+        self.word_address(addr, &mut word_addr);
+
+        let mut buf = [0u8; MAX_ADDRESS_BYTES + 1];
+        buf[0..D::ADDRESS_BYTES].copy_from_slice(&word_addr[0..D::ADDRESS_BYTES]);
+        buf[D::ADDRESS_BYTES] = byte;
+
+        self.wp.set_low().ok();
+        let result = self.i2c.write(device_address, &buf[0..D::ADDRESS_BYTES + 1]).await;
+        self.wp.set_high().ok();
+        result.map_err(AsyncError::I2c)?;
+
+        self.ack_delay().await;
+        Ok(())
+    }
+
+    pub async fn write_data(&mut self, addr: u32, data: &[u8]) -> Result<(), AsyncError<I2C::Error>> {
+        if addr as usize > D::CAPACITY {
+            return Err(AsyncError::OutOfRange);
+        }
+
+        let len = data.len();
+        if addr as usize + len > D::CAPACITY {
+            return Err(AsyncError::TooMuchData);
+        }
+
+        let mut addr: u32 = addr;
+        let mut writebuf = [0u8; MAX_ADDRESS_BYTES + MAX_PAGE_SIZE];
+        let mut wrptr: usize = 0;
+        while wrptr < data.len() {
+            let pagesize = page_chunk_len(addr, len - wrptr, D::PAGE_SIZE);
+
+            let device_address = self.device_address(addr);
+            let mut word_addr = [0u8; MAX_ADDRESS_BYTES];
+            self.word_address(addr, &mut word_addr);
+
+            writebuf[0..D::ADDRESS_BYTES].clone_from_slice(&word_addr[0..D::ADDRESS_BYTES]);
+            writebuf[D::ADDRESS_BYTES..D::ADDRESS_BYTES + pagesize]
+                .clone_from_slice(&data[wrptr..wrptr + pagesize]);
+
+            self.wp.set_low().ok();
+            let result = self
+                .i2c
+                .write(device_address, &writebuf[0..pagesize + D::ADDRESS_BYTES])
+                .await;
+            self.wp.set_high().ok();
+            result.map_err(AsyncError::I2c)?;
+
+            self.ack_delay().await;
+
+            addr += pagesize as u32;
+            wrptr += pagesize;
+        }
+
+        Ok(())
+    }
+
+    pub async fn read_byte(&mut self, addr: u32) -> Result<u8, AsyncError<I2C::Error>> {
+        if addr as usize > D::CAPACITY {
+            return Err(AsyncError::OutOfRange);
+        }
+        if addr as usize + 1 > D::CAPACITY {
+            return Err(AsyncError::TooMuchData);
+        }
+
+        let device_address = self.device_address(addr);
+        let mut word_addr = [0u8; MAX_ADDRESS_BYTES];
+        self.word_address(addr, &mut word_addr);
+
+        let mut byte: [u8; 1] = [0];
+        self.i2c
+            .write_read(device_address, &word_addr[0..D::ADDRESS_BYTES], &mut byte)
+            .await
+            .map_err(AsyncError::I2c)?;
+        Ok(byte[0])
+    }
+
+    pub async fn read_data(&mut self, addr: u32, data: &mut [u8]) -> Result<(), AsyncError<I2C::Error>> {
+        if addr as usize > D::CAPACITY {
+            return Err(AsyncError::OutOfRange);
+        }
+        if addr as usize + data.len() > D::CAPACITY {
+            return Err(AsyncError::TooMuchData);
+        }
+
+        let mut addr: u32 = addr;
+        let mut rdptr: usize = 0;
+        while rdptr < data.len() {
+            let chunk = block_chunk_len(addr, data.len() - rdptr);
+
+            let device_address = self.device_address(addr);
+            let mut word_addr = [0u8; MAX_ADDRESS_BYTES];
+            self.word_address(addr, &mut word_addr);
+
+            self.i2c
+                .write_read(device_address, &word_addr[0..D::ADDRESS_BYTES], &mut data[rdptr..rdptr + chunk])
+                .await
+                .map_err(AsyncError::I2c)?;
+
+            addr += chunk as u32;
+            rdptr += chunk;
+        }
+
+        Ok(())
+    }
+
+    /// Reads the factory-programmed unique ID / EUI-48 MAC address from `D::EUI48_OFFSET`,
+    /// without the caller needing to know the magic offset. Useful for board bring-up.
+    pub async fn read_eui48(&mut self) -> Result<[u8; 6], AsyncError<I2C::Error>> {
+        let mut id = [0u8; 6];
+        self.read_data(D::EUI48_OFFSET as u32, &mut id).await?;
+        Ok(id)
+    }
+}
@@ -0,0 +1,46 @@
+//! Descriptors for the specific 24xx-family parts this driver can talk to.
+//!
+//! The 24xx family shares a single access protocol but disagrees on capacity, page size, and
+//! how many bytes of word address it expects. Parts above 64 KiB additionally run out of word
+//! address bits and instead encode the extra high bits into the I2C device address itself (the
+//! block-select bits OR'd into the base `0x50` range).
+
+/// Describes the capacity, page size, and address width of a specific 24xx-family part.
+pub trait DeviceKind {
+    /// Total storage capacity, in bytes.
+    const CAPACITY: usize;
+    /// Size of a single write page, in bytes.
+    const PAGE_SIZE: usize;
+    /// Number of bytes used to encode a word address (1 or 2).
+    const ADDRESS_BYTES: usize;
+    /// Offset of the factory-programmed unique ID / EUI-48 some 24xx parts (the "24AA02E48"
+    /// family, and board EEPROMs on Zynq/STM32H7 designs) carry in a reserved region near the
+    /// top of the array. `0xFA` is the common placement for the 6-byte EUI-48.
+    const EUI48_OFFSET: usize = 0xFA;
+}
+
+/// 24C02: 2 Kbit (256 B), 8-byte pages, single address byte.
+pub struct Mcp24Lc02;
+impl DeviceKind for Mcp24Lc02 {
+    const CAPACITY: usize = 256;
+    const PAGE_SIZE: usize = 8;
+    const ADDRESS_BYTES: usize = 1;
+}
+
+/// 24LC512: 512 Kbit (64 KB), 128-byte pages, two address bytes. The original target of this
+/// driver and the default device kind for backwards compatibility.
+pub struct Mcp24Lc512;
+impl DeviceKind for Mcp24Lc512 {
+    const CAPACITY: usize = 65_536;
+    const PAGE_SIZE: usize = 128;
+    const ADDRESS_BYTES: usize = 2;
+}
+
+/// 24CM01: 1 Mbit (128 KB), 256-byte pages. Exceeds 64 KiB, so the top address bit is encoded
+/// into the I2C device address rather than the word address.
+pub struct Mcp24Cm01;
+impl DeviceKind for Mcp24Cm01 {
+    const CAPACITY: usize = 131_072;
+    const PAGE_SIZE: usize = 256;
+    const ADDRESS_BYTES: usize = 2;
+}
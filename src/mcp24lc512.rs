@@ -1,37 +1,122 @@
+use core::marker::PhantomData;
 use embedded_hal::blocking::i2c::{Write, WriteRead};
 use embedded_hal::digital::v2::OutputPin;
-use embedded_time::duration::Milliseconds;
+use embedded_time::duration::{Generic, Milliseconds};
 use embedded_time::Clock;
 
-const AVAILABLE_STORAGE: usize = 64_000;
-const PAGESIZE: usize = 128;
+pub mod asynch;
+pub mod device;
+mod storage;
+use device::{DeviceKind, Mcp24Lc512};
+
 const DEFAULT_ADDRESS: u8 = 0x50;
+const DEFAULT_ACK_POLL_TIMEOUT_MS: u32 = 10;
+const FIXED_DELAY_MS: u32 = 5;
+/// Largest page size among the devices in [`device`], used to size the write scratch buffer.
+const MAX_PAGE_SIZE: usize = 256;
+/// Largest address width, in bytes, among the devices in [`device`].
+const MAX_ADDRESS_BYTES: usize = 2;
+
+/// How many bytes can be written starting at `addr` before crossing a page boundary, capped by
+/// `remaining`. Shared between the blocking and async drivers.
+pub(crate) fn page_chunk_len(addr: u32, remaining: usize, page_size: usize) -> usize {
+    let maxsize = page_size - (addr as usize % page_size);
+    remaining.min(maxsize)
+}
+
+/// How many bytes can be read or written starting at `addr` before crossing a 64 KiB
+/// block-select boundary, capped by `remaining`. Devices over 64 KiB encode the high address
+/// bits into the I2C device address (see `device_address`), so every access must stay within a
+/// single block. Shared between the blocking and async drivers.
+pub(crate) fn block_chunk_len(addr: u32, remaining: usize) -> usize {
+    let block_remaining = 0x1_0000 - (addr as usize % 0x1_0000);
+    remaining.min(block_remaining)
+}
 
 ///Errors
 pub enum Error<E : Write + WriteRead> {
     OutOfRange,
     TooMuchData,
+    Timeout,
     I2cWriteRead(<E as WriteRead>::Error),
     I2cWrite(<E as Write>::Error),
 }
 
-pub struct Eeprom<'a, I2C : Write + WriteRead, WP: OutputPin, CLOCK: Clock> {
+/// How `ack_polling` waits for the device to finish its internal write cycle.
+pub enum AckMode {
+    /// Repeatedly address the device and treat a NACK as "still writing", until it ACKs or
+    /// `timeout` elapses. This is the fast path and works on most I2C peripherals.
+    Polling { timeout: Milliseconds<u32> },
+    /// Just wait a fixed delay instead of polling. Some I2C peripherals (e.g. certain STM32
+    /// I2C blocks) cannot cleanly retry a NACKed address and need this fallback.
+    FixedDelay { delay: Milliseconds<u32> },
+}
+
+impl Default for AckMode {
+    fn default() -> Self {
+        AckMode::Polling {
+            timeout: Milliseconds::new(DEFAULT_ACK_POLL_TIMEOUT_MS),
+        }
+    }
+}
+
+pub struct Eeprom<'a, I2C : Write + WriteRead, WP: OutputPin, CLOCK: Clock, D: DeviceKind = Mcp24Lc512> {
     address: u8,
     i2c: I2C,
     wp: WP,
     clock: &'a CLOCK,
+    ack_mode: AckMode,
+    _device: PhantomData<D>,
 }
 
-impl<'a, I2C: Write + WriteRead, WP: OutputPin, CLOCK: Clock> Eeprom<'a, I2C, WP, CLOCK> {
+impl<'a, I2C: Write + WriteRead, WP: OutputPin, CLOCK: Clock, D: DeviceKind> Eeprom<'a, I2C, WP, CLOCK, D> {
     pub fn new(i2c : I2C, wp: WP, clock: &'a CLOCK) -> Self {
         Eeprom {
             i2c,
             address: DEFAULT_ADDRESS,
             wp,
             clock,
+            ack_mode: AckMode::default(),
+            _device: PhantomData,
         }
     }
 
+    /// Effective I2C device address for `addr`, with the high address bits that do not fit in
+    /// the word address OR'd in as block-select bits (only relevant for devices over 64 KiB).
+    fn device_address(&self, addr: u32) -> u8 {
+        if D::CAPACITY > 0x1_0000 {
+            self.address | ((addr >> 16) as u8)
+        } else {
+            self.address
+        }
+    }
+
+    /// Encodes the word-address portion of `addr` into `buf[0..D::ADDRESS_BYTES]`.
+    fn word_address(&self, addr: u32, buf: &mut [u8; MAX_ADDRESS_BYTES]) {
+        if D::ADDRESS_BYTES == 1 {
+            buf[0] = addr as u8;
+        } else {
+            buf[0..2].copy_from_slice(&(addr as u16).to_be_bytes());
+        }
+    }
+
+    /// Use ack-polling with a caller-supplied timeout, overriding the default
+    /// `DEFAULT_ACK_POLL_TIMEOUT_MS`. Use this when a page write is expected to take longer (or
+    /// shorter) than the default allows.
+    pub fn with_ack_timeout(mut self, timeout: Milliseconds<u32>) -> Self {
+        self.ack_mode = AckMode::Polling { timeout };
+        self
+    }
+
+    /// Fall back to waiting a fixed delay after each page write instead of ack-polling, for
+    /// I2C peripherals that cannot retry a NACKed address cleanly.
+    pub fn with_fixed_delay_ack(mut self) -> Self {
+        self.ack_mode = AckMode::FixedDelay {
+            delay: Milliseconds::new(FIXED_DELAY_MS),
+        };
+        self
+    }
+
     fn with_wp_low<F, T>(&mut self, f: F) -> T
     where
         F: FnOnce(&mut Self) -> T,
@@ -43,38 +128,70 @@ impl<'a, I2C: Write + WriteRead, WP: OutputPin, CLOCK: Clock> Eeprom<'a, I2C, WP
         result
     }
 
-    //TODO: AFAIK STM32 I2C modules do not allow proper ack polling, so I need to replace it with an adequately long delay
-    pub fn ack_polling(&mut self) -> Result<(), Error<I2C>> {
-        self.clock
-            .new_timer(Milliseconds::new(5))
-            .start()
-            .ok()
-            .unwrap()
-            .wait()
-            .ok()
-            .unwrap();
+    /// Wait for the device's internal write cycle to complete. While it is in progress the
+    /// EEPROM NACKs its own address, so this retries an address-only write to `device_address`
+    /// until it ACKs (or falls back to a fixed delay, see `AckMode`).
+    pub fn ack_polling(&mut self, device_address: u8) -> Result<(), Error<I2C>> {
+        match self.ack_mode {
+            AckMode::FixedDelay { delay } => {
+                self.clock
+                    .new_timer(delay)
+                    .start()
+                    .ok()
+                    .unwrap()
+                    .wait()
+                    .ok()
+                    .unwrap();
 
-        Ok(())
+                Ok(())
+            }
+            AckMode::Polling { timeout } => {
+                let start = self.clock.try_now().map_err(|_| Error::Timeout)?;
+                // Compare in `Generic`, the clock-agnostic duration representation, so this
+                // doesn't need a `Milliseconds: TryFrom<Generic<CLOCK::T>>` bound on CLOCK.
+                let timeout: Generic<u32> = timeout.into();
+
+                loop {
+                    if self.i2c.write(device_address, &[]).is_ok() {
+                        return Ok(());
+                    }
+
+                    let now = self.clock.try_now().map_err(|_| Error::Timeout)?;
+                    let elapsed = now.checked_duration_since(&start).ok_or(Error::Timeout)?;
+
+                    if elapsed >= timeout {
+                        return Err(Error::Timeout);
+                    }
+                }
+            }
+        }
     }
 
     pub fn write_byte(
         &mut self,
-        addr: u16,
+        addr: u32,
         byte: u8,
     ) -> Result<(), Error<I2C>> {
-        if addr as usize > AVAILABLE_STORAGE {
+        if addr as usize >= D::CAPACITY {
             return Err(Error::OutOfRange);
         }
 
-        if addr as usize + 1 > AVAILABLE_STORAGE {
+        if addr as usize + 1 > D::CAPACITY {
             return Err(Error::TooMuchData);
         }
 
-        let addr = addr.to_be_bytes();
+        let device_address = self.device_address(addr);
+        let mut word_addr = [0u8; MAX_ADDRESS_BYTES];
+        self.word_address(addr, &mut word_addr);
+
+        let mut buf = [0u8; MAX_ADDRESS_BYTES + 1];
+        buf[0..D::ADDRESS_BYTES].copy_from_slice(&word_addr[0..D::ADDRESS_BYTES]);
+        buf[D::ADDRESS_BYTES] = byte;
 
         self.with_wp_low(|eeprom| {
-            eeprom.i2c.write(eeprom.address, &[addr[0], addr[1], byte])
-                .map_err(Error::I2cWrite)
+            eeprom.i2c.write(device_address, &buf[0..D::ADDRESS_BYTES + 1])
+                .map_err(Error::I2cWrite)?;
+            eeprom.ack_polling(device_address)
         })?;
 
         Ok(())
@@ -82,74 +199,266 @@ impl<'a, I2C: Write + WriteRead, WP: OutputPin, CLOCK: Clock> Eeprom<'a, I2C, WP
 
     pub fn write_data(
         &mut self,
-        addr: u16,
+        addr: u32,
         data: &[u8],
     ) -> Result<(), Error<I2C>> {
-        if addr as usize > AVAILABLE_STORAGE {
+        if addr as usize > D::CAPACITY {
             return Err(Error::OutOfRange);
         }
 
         let len = data.len();
-        if addr as usize + len > AVAILABLE_STORAGE {
+        if addr as usize + len > D::CAPACITY {
             return Err(Error::TooMuchData);
         }
 
-        let mut addr: u16 = addr;
-        let mut writebuf: [u8; PAGESIZE + 2] = [0; PAGESIZE + 2];
+        let mut addr: u32 = addr;
+        let mut writebuf: [u8; MAX_ADDRESS_BYTES + MAX_PAGE_SIZE] = [0; MAX_ADDRESS_BYTES + MAX_PAGE_SIZE];
         let mut wrptr: usize = 0;
         while wrptr < data.len() {
-            let index: usize = addr as usize;
-            let maxsize: usize = PAGESIZE - (index % PAGESIZE);
-            let pagesize = if (len - wrptr) < maxsize {
-                len - wrptr
-            } else {
-                maxsize
-            };
+            let pagesize = page_chunk_len(addr, len - wrptr, D::PAGE_SIZE);
+
+            let device_address = self.device_address(addr);
+            let mut word_addr = [0u8; MAX_ADDRESS_BYTES];
+            self.word_address(addr, &mut word_addr);
 
-            writebuf[0..2].clone_from_slice(&addr.to_be_bytes());
-            writebuf[2..2 + pagesize].clone_from_slice(&data[wrptr..wrptr + pagesize]);
+            writebuf[0..D::ADDRESS_BYTES].clone_from_slice(&word_addr[0..D::ADDRESS_BYTES]);
+            writebuf[D::ADDRESS_BYTES..D::ADDRESS_BYTES + pagesize]
+                .clone_from_slice(&data[wrptr..wrptr + pagesize]);
 
             self.with_wp_low(|eeprom| {
-                eeprom.i2c.write(eeprom.address, &writebuf[0..pagesize + 2])
+                eeprom.i2c.write(device_address, &writebuf[0..pagesize + D::ADDRESS_BYTES])
                     .map_err(Error::I2cWrite)?;
-                eeprom.ack_polling()
+                eeprom.ack_polling(device_address)
             })?;
 
-            addr += pagesize as u16;
+            addr += pagesize as u32;
             wrptr += pagesize;
         }
 
         Ok(())
     }
 
-    pub fn read_byte(&mut self, addr: u16) -> Result<u8, Error<I2C>> {
-        if addr as usize > AVAILABLE_STORAGE {
+    pub fn read_byte(&mut self, addr: u32) -> Result<u8, Error<I2C>> {
+        if addr as usize >= D::CAPACITY {
             return Err(Error::OutOfRange);
         }
 
-        if addr as usize + 1 > AVAILABLE_STORAGE {
+        if addr as usize + 1 > D::CAPACITY {
             return Err(Error::TooMuchData);
         }
+
+        let device_address = self.device_address(addr);
+        let mut word_addr = [0u8; MAX_ADDRESS_BYTES];
+        self.word_address(addr, &mut word_addr);
+
         let mut byte: [u8; 1] = [0];
-        self.i2c.write_read(self.address, &addr.to_be_bytes(), &mut byte)
+        self.i2c.write_read(device_address, &word_addr[0..D::ADDRESS_BYTES], &mut byte)
             .map_err(Error::I2cWriteRead)?;
         Ok(byte[0])
     }
 
     pub fn read_data(
         &mut self,
-        addr: u16,
+        addr: u32,
         data: &mut [u8],
     ) -> Result<(), Error<I2C>> {
-        if addr as usize > AVAILABLE_STORAGE {
+        if addr as usize > D::CAPACITY {
             return Err(Error::OutOfRange);
         }
 
-        if addr as usize + data.len() > AVAILABLE_STORAGE {
+        if addr as usize + data.len() > D::CAPACITY {
             return Err(Error::TooMuchData);
         }
-        self.i2c.write_read(self.address, &addr.to_be_bytes(), data)
-            .map_err(Error::I2cWriteRead)?;
+
+        let mut addr: u32 = addr;
+        let mut rdptr: usize = 0;
+        while rdptr < data.len() {
+            let chunk = block_chunk_len(addr, data.len() - rdptr);
+
+            let device_address = self.device_address(addr);
+            let mut word_addr = [0u8; MAX_ADDRESS_BYTES];
+            self.word_address(addr, &mut word_addr);
+
+            self.i2c
+                .write_read(device_address, &word_addr[0..D::ADDRESS_BYTES], &mut data[rdptr..rdptr + chunk])
+                .map_err(Error::I2cWriteRead)?;
+
+            addr += chunk as u32;
+            rdptr += chunk;
+        }
+
         Ok(())
     }
+
+    /// Reads the factory-programmed unique ID / EUI-48 MAC address from `D::EUI48_OFFSET`,
+    /// without the caller needing to know the magic offset. Useful for board bring-up.
+    pub fn read_eui48(&mut self) -> Result<[u8; 6], Error<I2C>> {
+        let mut id = [0u8; 6];
+        self.read_data(D::EUI48_OFFSET as u32, &mut id)?;
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use device::Mcp24Cm01;
+    use embedded_time::fraction::Fraction;
+    use embedded_time::Instant;
+    use std::cell::Cell;
+    use std::collections::VecDeque;
+
+    #[test]
+    fn page_chunk_len_stops_at_page_boundary() {
+        assert_eq!(page_chunk_len(120, 100, 128), 8);
+        assert_eq!(page_chunk_len(0, 100, 128), 100);
+        assert_eq!(page_chunk_len(128, 100, 128), 100);
+    }
+
+    #[test]
+    fn block_chunk_len_stops_at_64kib_boundary() {
+        assert_eq!(block_chunk_len(0xFFF0, 32), 0x10);
+        assert_eq!(block_chunk_len(0x10000, 32), 32);
+        assert_eq!(block_chunk_len(0, 0x20000), 0x10000);
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Transaction {
+        Write { address: u8, bytes: Vec<u8> },
+        WriteRead { address: u8, bytes: Vec<u8>, response: Vec<u8> },
+    }
+
+    struct MockI2c {
+        expected: VecDeque<Transaction>,
+    }
+
+    impl MockI2c {
+        fn new(expected: Vec<Transaction>) -> Self {
+            MockI2c { expected: expected.into() }
+        }
+
+        fn done(&self) {
+            assert!(self.expected.is_empty(), "not all expected I2C transactions occurred");
+        }
+    }
+
+    impl Write for MockI2c {
+        type Error = ();
+
+        fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), ()> {
+            match self.expected.pop_front() {
+                Some(Transaction::Write { address: a, bytes: b }) if a == address && b == bytes => Ok(()),
+                other => panic!("unexpected write({:#x}, {:?}), expected {:?}", address, bytes, other),
+            }
+        }
+    }
+
+    impl WriteRead for MockI2c {
+        type Error = ();
+
+        fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), ()> {
+            match self.expected.pop_front() {
+                Some(Transaction::WriteRead { address: a, bytes: b, response }) if a == address && b == bytes => {
+                    buffer.copy_from_slice(&response);
+                    Ok(())
+                }
+                other => panic!("unexpected write_read({:#x}, {:?}), expected {:?}", address, bytes, other),
+            }
+        }
+    }
+
+    /// I2C stub whose address-only writes always NACK, to drive `ack_polling` into its timeout
+    /// path deterministically.
+    struct NeverAcksI2c;
+
+    impl Write for NeverAcksI2c {
+        type Error = ();
+        fn write(&mut self, _address: u8, _bytes: &[u8]) -> Result<(), ()> {
+            Err(())
+        }
+    }
+
+    impl WriteRead for NeverAcksI2c {
+        type Error = ();
+        fn write_read(&mut self, _address: u8, _bytes: &[u8], _buffer: &mut [u8]) -> Result<(), ()> {
+            Err(())
+        }
+    }
+
+    struct FakePin;
+    impl OutputPin for FakePin {
+        type Error = ();
+        fn set_low(&mut self) -> Result<(), ()> {
+            Ok(())
+        }
+        fn set_high(&mut self) -> Result<(), ()> {
+            Ok(())
+        }
+    }
+
+    /// Clock that advances by one millisecond on every `try_now` call, so a bounded number of
+    /// `ack_polling` retries deterministically exhausts any timeout.
+    struct FakeClock {
+        ticks: Cell<u32>,
+    }
+
+    impl Clock for FakeClock {
+        type T = u32;
+        const SCALING_FACTOR: Fraction = Fraction::new(1, 1_000);
+
+        fn try_now(&self) -> Result<Instant<Self>, embedded_time::clock::Error> {
+            let t = self.ticks.get();
+            self.ticks.set(t + 1);
+            Ok(Instant::new(t))
+        }
+    }
+
+    #[test]
+    fn read_data_splits_across_block_select_boundary() {
+        let i2c = MockI2c::new(vec![
+            Transaction::WriteRead {
+                address: 0x50,
+                bytes: vec![0xFF, 0xF0],
+                response: vec![0; 0x10],
+            },
+            Transaction::WriteRead {
+                address: 0x51,
+                bytes: vec![0x00, 0x00],
+                response: vec![0; 0x10],
+            },
+        ]);
+        let clock = FakeClock { ticks: Cell::new(0) };
+        let mut eeprom: Eeprom<_, _, _, Mcp24Cm01> = Eeprom::new(i2c, FakePin, &clock);
+
+        let mut data = [0u8; 0x20];
+        eeprom.read_data(0xFFF0, &mut data).ok().unwrap();
+        eeprom.i2c.done();
+    }
+
+    #[test]
+    fn read_eui48_reads_six_bytes_at_device_offset() {
+        let i2c = MockI2c::new(vec![Transaction::WriteRead {
+            address: 0x50,
+            bytes: vec![0x00, 0xFA],
+            response: vec![1, 2, 3, 4, 5, 6],
+        }]);
+        let clock = FakeClock { ticks: Cell::new(0) };
+        let mut eeprom: Eeprom<_, _, _, Mcp24Lc512> = Eeprom::new(i2c, FakePin, &clock);
+
+        let id = eeprom.read_eui48().ok().unwrap();
+        assert_eq!(id, [1, 2, 3, 4, 5, 6]);
+        eeprom.i2c.done();
+    }
+
+    #[test]
+    fn ack_polling_times_out_when_the_device_never_acks() {
+        let clock = FakeClock { ticks: Cell::new(0) };
+        let mut eeprom: Eeprom<_, _, _, Mcp24Lc512> =
+            Eeprom::new(NeverAcksI2c, FakePin, &clock).with_ack_timeout(Milliseconds::new(5));
+
+        match eeprom.ack_polling(0x50) {
+            Err(Error::Timeout) => {}
+            _ => panic!("expected ack_polling to time out"),
+        }
+    }
 }
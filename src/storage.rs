@@ -0,0 +1,33 @@
+//! Adapter exposing [`Eeprom`] through the `embedded-storage` abstraction, so it can be dropped
+//! into filesystem/key-value layers and other generic persistence code written against
+//! `embedded_storage::{ReadStorage, Storage}` instead of this crate's own API.
+
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use embedded_hal::digital::v2::OutputPin;
+use embedded_storage::{ReadStorage, Storage};
+use embedded_time::Clock;
+
+use crate::device::DeviceKind;
+use crate::{Eeprom, Error};
+
+impl<'a, I2C: Write + WriteRead, WP: OutputPin, CLOCK: Clock, D: DeviceKind> ReadStorage
+    for Eeprom<'a, I2C, WP, CLOCK, D>
+{
+    type Error = Error<I2C>;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        self.read_data(offset, bytes)
+    }
+
+    fn capacity(&self) -> usize {
+        D::CAPACITY
+    }
+}
+
+impl<'a, I2C: Write + WriteRead, WP: OutputPin, CLOCK: Clock, D: DeviceKind> Storage
+    for Eeprom<'a, I2C, WP, CLOCK, D>
+{
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.write_data(offset, bytes)
+    }
+}